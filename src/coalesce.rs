@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::routing::PatternSet;
+use crate::{ConfigError, DecodeMode, Distributor, OscMessage, OscPayload, PacketSink};
+
+// 同一位址在 interval 內只保留最新值，下一次 tick 才送出一次。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoalesceConfig {
+    /// 節流間隔，例如 `"20ms"`、`"1s"`。
+    pub interval: String,
+    /// 一律立即轉發、不做節流的位址樣式。
+    #[serde(default)]
+    pub passthrough: Vec<String>,
+}
+
+impl CoalesceConfig {
+    pub fn parsed_interval(&self) -> Result<Duration, ConfigError> {
+        parse_interval(&self.interval)
+    }
+}
+
+fn parse_interval(raw: &str) -> Result<Duration, ConfigError> {
+    let trimmed = raw.trim();
+
+    if let Some(ms) = trimmed.strip_suffix("ms") {
+        ms.trim()
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| ConfigError::InvalidInterval(raw.to_string()))
+    } else if let Some(secs) = trimmed.strip_suffix('s') {
+        secs.trim()
+            .parse::<f64>()
+            .map(Duration::from_secs_f64)
+            .map_err(|_| ConfigError::InvalidInterval(raw.to_string()))
+    } else {
+        trimmed
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| ConfigError::InvalidInterval(raw.to_string()))
+    }
+}
+
+// 坐落在 `Receiver`/`TcpReceiver` 與 `Distributor` 之間的節流級。
+pub struct Coalescer {
+    sink: Arc<Distributor>,
+    pending: Mutex<HashMap<String, OscPayload>>,
+    passthrough: PatternSet,
+}
+
+impl Coalescer {
+    pub fn spawn(sink: Arc<Distributor>, interval: Duration, passthrough: &[String]) -> Arc<Self> {
+        let coalescer = Arc::new(Self {
+            sink,
+            pending: Mutex::new(HashMap::new()),
+            passthrough: PatternSet::new(passthrough),
+        });
+
+        let ticking = coalescer.clone();
+        tokio::spawn(async move {
+            ticking.run(interval).await;
+        });
+
+        coalescer
+    }
+
+    async fn run(&self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            self.flush();
+        }
+    }
+
+    fn flush(&self) {
+        let mut pending = self.pending.lock().expect("coalescer mutex poisoned");
+        for (_, payload) in pending.drain() {
+            self.sink.send(payload);
+        }
+    }
+}
+
+impl PacketSink for Coalescer {
+    fn send(&self, payload: OscPayload) {
+        // 用 Lenient 解析：封包可能已經在接收端依 lenient 的 DecodeMode 通過
+        // 驗證，這裡用 Strict 重新解析會讓這類封包在此解析失敗、直接轉發而跳過節流。
+        let address = match OscMessage::deserialize_with_mode(&payload, DecodeMode::Lenient) {
+            Ok(message) => message.address,
+            Err(e) => {
+                eprintln!("合併節流時解析封包失敗: {}", e);
+                self.sink.send(payload);
+                return;
+            }
+        };
+
+        if self.passthrough.matches(&address) {
+            self.sink.send(payload);
+            return;
+        }
+
+        self.pending
+            .lock()
+            .expect("coalescer mutex poisoned")
+            .insert(address, payload);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_milliseconds() {
+        assert_eq!(parse_interval("20ms").unwrap(), Duration::from_millis(20));
+        assert_eq!(parse_interval(" 5 ms ").unwrap(), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn parses_seconds() {
+        assert_eq!(parse_interval("1s").unwrap(), Duration::from_secs(1));
+        assert_eq!(parse_interval("0.5s").unwrap(), Duration::from_secs_f64(0.5));
+    }
+
+    #[test]
+    fn bare_integer_is_milliseconds() {
+        assert_eq!(parse_interval("20").unwrap(), Duration::from_millis(20));
+    }
+
+    #[test]
+    fn rejects_invalid_interval() {
+        assert!(parse_interval("not-a-duration").is_err());
+        assert!(parse_interval("").is_err());
+    }
+
+    fn make_coalescer(passthrough: &[String]) -> (Arc<Coalescer>, Arc<Distributor>) {
+        let distributor = Arc::new(Distributor::new(&[]));
+        let coalescer = Arc::new(Coalescer {
+            sink: distributor.clone(),
+            pending: Mutex::new(HashMap::new()),
+            passthrough: PatternSet::new(passthrough),
+        });
+        (coalescer, distributor)
+    }
+
+    #[test]
+    fn keeps_only_the_most_recent_payload_per_address_until_flush() {
+        let (coalescer, distributor) = make_coalescer(&[]);
+        let mut rx = distributor.subscribe();
+
+        coalescer.send(Arc::from(OscMessage::new("/a").push_int(1).serialize()));
+        coalescer.send(Arc::from(OscMessage::new("/a").push_int(2).serialize()));
+        assert!(rx.try_recv().is_err());
+
+        coalescer.flush();
+
+        let payload = rx.try_recv().expect("flush should forward the pending message");
+        let message = OscMessage::deserialize(&payload).unwrap();
+        assert_eq!(message, OscMessage::new("/a").push_int(2));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn passthrough_pattern_bypasses_coalescing() {
+        let (coalescer, distributor) = make_coalescer(&["/pass".to_string()]);
+        let mut rx = distributor.subscribe();
+
+        coalescer.send(Arc::from(OscMessage::new("/pass").serialize()));
+
+        let payload = rx.try_recv().expect("passthrough should forward immediately");
+        assert_eq!(OscMessage::deserialize(&payload).unwrap().address, "/pass");
+    }
+
+    #[test]
+    fn lenient_only_payload_is_still_coalesced_by_address() {
+        let (coalescer, distributor) = make_coalescer(&[]);
+        let mut rx = distributor.subscribe();
+
+        // type tag 字串 ",N"：Strict 不認得 'N'（Nil）會直接失敗，Lenient 則會跳過
+        let mut bytes = b"/a\0\0".to_vec();
+        bytes.extend_from_slice(b",N\0\0");
+        coalescer.send(Arc::from(bytes));
+        assert!(rx.try_recv().is_err());
+
+        coalescer.flush();
+        assert!(rx.try_recv().is_ok());
+    }
+}
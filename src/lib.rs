@@ -4,13 +4,98 @@ use tokio::sync::broadcast;
 use thiserror::Error;
 use serde::{Deserialize, Serialize};
 
+pub mod coalesce;
+pub mod mqtt;
+pub mod routing;
+pub mod tcp;
+pub mod transport;
+pub mod ws;
+
+use async_trait::async_trait;
+use coalesce::{CoalesceConfig, Coalescer};
+use mqtt::{MqttBridge, MqttConfig};
+use routing::Route;
+use tcp::{TcpReceiver, TcpSender, TcpSenderError};
+use transport::{DynTransport, Transport};
+use ws::WsRelay;
+
+// 接收端與中介節流級（`Coalescer`）共用的介面，當作封包的去處
+pub trait PacketSink: Send + Sync {
+    fn send(&self, payload: OscPayload);
+}
+
+impl PacketSink for Distributor {
+    fn send(&self, payload: OscPayload) {
+        Distributor::send(self, payload)
+    }
+}
+
 const MAX_PACKET_SIZE: usize = 65507;
-type OscPayload = Arc<[u8]>;
+// TCP 沒有 UDP 封包的天然上限，給一個寬鬆的預設值防惡意長度前綴
+const DEFAULT_MAX_TCP_PACKET_LEN: usize = 1_048_576;
+pub(crate) type OscPayload = Arc<[u8]>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    Udp,
+    Tcp,
+}
+
+impl Default for TransportKind {
+    fn default() -> Self {
+        TransportKind::Udp
+    }
+}
+
+fn default_max_packet_len() -> usize {
+    DEFAULT_MAX_TCP_PACKET_LEN
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenPort {
+    pub port: u16,
+    #[serde(default)]
+    pub transport: TransportKind,
+    // 設定後會在這個埠口與 Distributor 之間插入一個合併級
+    #[serde(default)]
+    pub coalesce: Option<CoalesceConfig>,
+    #[serde(default)]
+    pub decode_mode: DecodeMode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Target {
+    pub addr: SocketAddr,
+    #[serde(default)]
+    pub transport: TransportKind,
+    // OSC 位址萬用字元樣式，只有符合的訊息才會送到這個目標；留空表示全部放行
+    #[serde(rename = "match", default)]
+    pub match_patterns: Vec<String>,
+    #[serde(default)]
+    pub scale: Option<f64>,
+    // 在 scale 之後套用
+    #[serde(default)]
+    pub offset: Option<f64>,
+}
+
+impl Target {
+    fn route(&self) -> Route {
+        Route::new(&self.match_patterns, self.scale, self.offset)
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    pub listen_ports: Vec<u16>,
-    pub targets: Vec<SocketAddr>,
+    pub listen_ports: Vec<ListenPort>,
+    pub targets: Vec<Target>,
+    // TCP 連線允許的最大封包長度，防止惡意或錯誤的長度前綴耗盡記憶體
+    #[serde(default = "default_max_packet_len")]
+    pub max_packet_len: usize,
+    #[serde(default)]
+    pub mqtt: Option<MqttConfig>,
+    #[serde(default)]
+    pub ws_bind: Option<SocketAddr>,
 }
 
 impl Config {
@@ -25,17 +110,20 @@ impl Config {
         if self.listen_ports.is_empty() {
             return Err(ConfigError::NoListenPorts);
         }
-        
+
         if self.targets.is_empty() {
             return Err(ConfigError::NoTargets);
         }
-        
-        for port in &self.listen_ports {
-            if *port == 0 {
-                return Err(ConfigError::InvalidPort(*port));
+
+        for listen_port in &self.listen_ports {
+            if listen_port.port == 0 {
+                return Err(ConfigError::InvalidPort(listen_port.port));
+            }
+            if let Some(coalesce) = &listen_port.coalesce {
+                coalesce.parsed_interval()?;
             }
         }
-        
+
         Ok(())
     }
 }
@@ -52,15 +140,17 @@ pub enum ConfigError {
     NoTargets,
     #[error("無效的埠口: {0}")]
     InvalidPort(u16),
+    #[error("無效的時間間隔 \"{0}\"（範例：\"20ms\"、\"1s\"）")]
+    InvalidInterval(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct OscMessage {
     pub address: String,
     pub args: Vec<OscArg>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum OscArg {
     Int(i32),
     Float(f32),
@@ -68,6 +158,101 @@ pub enum OscArg {
     Bool(bool),
 }
 
+// 解析失敗時回報的結構化錯誤，帶著發生問題的位元組偏移量
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum OscParseError {
+    #[error("資料長度不足以解析 OSC 訊息（offset {offset}）")]
+    TooShort { offset: usize },
+    #[error("無效的位址格式（offset {offset}）")]
+    BadAddress { offset: usize },
+    #[error("字串未正確以 null 結尾（offset {offset}）")]
+    UnterminatedString { offset: usize },
+    #[error("未知的類型標籤 0x{tag:02x}（offset {offset}）")]
+    UnknownTypeTag { tag: u8, offset: usize },
+    #[error("參數資料不足（tag '{}' , offset {offset}）", *tag as char)]
+    TruncatedArg { tag: u8, offset: usize },
+}
+
+impl OscParseError {
+    pub fn offset(&self) -> usize {
+        match self {
+            OscParseError::TooShort { offset }
+            | OscParseError::BadAddress { offset }
+            | OscParseError::UnterminatedString { offset }
+            | OscParseError::UnknownTypeTag { offset, .. }
+            | OscParseError::TruncatedArg { offset, .. } => *offset,
+        }
+    }
+
+    // 給 dropped-by-reason 統計用的穩定字串鍵
+    pub fn reason(&self) -> &'static str {
+        match self {
+            OscParseError::TooShort { .. } => "too_short",
+            OscParseError::BadAddress { .. } => "bad_address",
+            OscParseError::UnterminatedString { .. } => "unterminated_string",
+            OscParseError::UnknownTypeTag { .. } => "unknown_type_tag",
+            OscParseError::TruncatedArg { .. } => "truncated_arg",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DecodeMode {
+    // 任何無法辨識的內容都視為錯誤，丟棄整則封包
+    Strict,
+    // 不帶資料的 'N'（Nil）與 'I'（Infinitum）標籤會被跳過，其餘已知類型的參數
+    // 仍會轉發；其他無法辨識的類型標籤與 Strict 一樣視為錯誤，丟棄整則封包
+    Lenient,
+}
+
+impl Default for DecodeMode {
+    fn default() -> Self {
+        DecodeMode::Strict
+    }
+}
+
+// 單一埠口的收送統計：收到幾筆、成功解析幾筆、各種原因各丟棄了幾筆
+#[derive(Debug, Default)]
+pub struct PortStats {
+    received: std::sync::atomic::AtomicU64,
+    parsed: std::sync::atomic::AtomicU64,
+    dropped: std::sync::Mutex<std::collections::HashMap<&'static str, u64>>,
+}
+
+impl PortStats {
+    fn record_received(&self) {
+        self.received
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn record_parsed(&self) {
+        self.parsed
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn record_dropped(&self, reason: &'static str) {
+        *self
+            .dropped
+            .lock()
+            .expect("port stats mutex poisoned")
+            .entry(reason)
+            .or_insert(0) += 1;
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn parsed(&self) -> u64 {
+        self.parsed.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn dropped_by_reason(&self) -> std::collections::HashMap<&'static str, u64> {
+        self.dropped.lock().expect("port stats mutex poisoned").clone()
+    }
+}
+
 impl OscMessage {
     pub fn new(address: impl Into<String>) -> Self {
         Self {
@@ -145,9 +330,14 @@ impl OscMessage {
         buffer
     }
 
-    pub fn deserialize(data: &[u8]) -> Result<Self, &'static str> {
+    // 等同於 deserialize_with_mode(data, DecodeMode::Strict)
+    pub fn deserialize(data: &[u8]) -> Result<Self, OscParseError> {
+        Self::deserialize_with_mode(data, DecodeMode::Strict)
+    }
+
+    pub fn deserialize_with_mode(data: &[u8], mode: DecodeMode) -> Result<Self, OscParseError> {
         if data.len() < 4 {
-            return Err("資料長度不足以解析 OSC 訊息");
+            return Err(OscParseError::TooShort { offset: 0 });
         }
 
         // Find address (null-terminated string)
@@ -155,40 +345,40 @@ impl OscMessage {
         while addr_end < data.len() && data[addr_end] != 0 {
             addr_end += 1;
         }
-        
+
         if addr_end >= data.len() || addr_end == 0 {
-            return Err("無效的位址格式");
+            return Err(OscParseError::BadAddress { offset: 0 });
         }
 
         let address = String::from_utf8_lossy(&data[..addr_end]).to_string();
-        
+
         // Skip to type tags (aligned to 4-byte boundary)
         let mut pos = ((addr_end + 1 + 3) / 4) * 4;
         if pos >= data.len() || data[pos] != b',' {
-            return Err("無效的類型標籤格式");
+            return Err(OscParseError::BadAddress { offset: pos });
         }
-        
+
         // Parse type tags
         let mut type_end = pos + 1;
         while type_end < data.len() && data[type_end] != 0 {
             type_end += 1;
         }
-        
+
         if type_end >= data.len() {
-            return Err("類型標籤未終止");
+            return Err(OscParseError::UnterminatedString { offset: pos + 1 });
         }
-        
+
         let type_tags = &data[pos + 1..type_end];
-        
+
         // Skip to arguments (aligned to 4-byte boundary)
         pos = ((type_end + 1 + 3) / 4) * 4;
-        
+
         let mut args = Vec::new();
         for &type_tag in type_tags {
             match type_tag {
                 b'i' => {
                     if pos + 4 > data.len() {
-                        return Err("整數參數資料不足");
+                        return Err(OscParseError::TruncatedArg { tag: type_tag, offset: pos });
                     }
                     let bytes = [data[pos], data[pos + 1], data[pos + 2], data[pos + 3]];
                     let value = i32::from_be_bytes(bytes);
@@ -197,7 +387,7 @@ impl OscMessage {
                 }
                 b'f' => {
                     if pos + 4 > data.len() {
-                        return Err("浮點數參數資料不足");
+                        return Err(OscParseError::TruncatedArg { tag: type_tag, offset: pos });
                     }
                     let bytes = [data[pos], data[pos + 1], data[pos + 2], data[pos + 3]];
                     let value = f32::from_be_bytes(bytes);
@@ -206,14 +396,14 @@ impl OscMessage {
                 }
                 b's' => {
                     if pos >= data.len() {
-                        return Err("字串參數資料不足");
+                        return Err(OscParseError::TruncatedArg { tag: type_tag, offset: pos });
                     }
                     let mut str_end = pos;
                     while str_end < data.len() && data[str_end] != 0 {
                         str_end += 1;
                     }
                     if str_end >= data.len() {
-                        return Err("字串參數未終止");
+                        return Err(OscParseError::UnterminatedString { offset: pos });
                     }
                     let value = String::from_utf8_lossy(&data[pos..str_end]).to_string();
                     args.push(OscArg::String(value));
@@ -223,44 +413,207 @@ impl OscMessage {
                     // Boolean values don't have data in the argument section
                     args.push(OscArg::Bool(type_tag == b'T'));
                 }
-                _ => return Err("未知的類型標籤"),
+                // OSC 規範定義 Nil/Infinitum 不帶任何參數資料，在 Lenient 模式下
+                // 可以安全跳過。其他未知標籤（例如標準的 `b`/`d`）可能帶有資料，
+                // 但本解析器不知道該跳過幾個位元組，貿然跳過會讓 `pos` 錯位、
+                // 汙染後續所有參數，所以一律視同 Strict 直接回報錯誤。
+                b'N' | b'I' if mode == DecodeMode::Lenient => {}
+                _ => {
+                    return Err(OscParseError::UnknownTypeTag {
+                        tag: type_tag,
+                        offset: pos,
+                    });
+                }
             }
         }
-        
+
         Ok(OscMessage { address, args })
     }
+
+    // 轉換為 {"address": "...", "args": [...]} 形式的 JSON，供 MQTT 橋接使用
+    pub fn to_json(&self) -> serde_json::Value {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| match arg {
+                OscArg::Int(value) => serde_json::json!(value),
+                OscArg::Float(value) => serde_json::json!(value),
+                OscArg::String(value) => serde_json::json!(value),
+                OscArg::Bool(value) => serde_json::json!(value),
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::json!({
+            "address": self.address,
+            "args": args,
+        })
+    }
+
+    // 從 to_json 產生的 JSON 還原成 OscMessage
+    pub fn from_json(value: &serde_json::Value) -> Result<Self, OscJsonError> {
+        let address = value
+            .get("address")
+            .and_then(|v| v.as_str())
+            .ok_or(OscJsonError::MissingAddress)?
+            .to_string();
+
+        let args = value
+            .get("args")
+            .and_then(|v| v.as_array())
+            .ok_or(OscJsonError::MissingArgs)?
+            .iter()
+            .map(|arg| {
+                if let Some(b) = arg.as_bool() {
+                    Ok(OscArg::Bool(b))
+                } else if let Some(i) = arg.as_i64() {
+                    Ok(OscArg::Int(i as i32))
+                } else if let Some(f) = arg.as_f64() {
+                    Ok(OscArg::Float(f as f32))
+                } else if let Some(s) = arg.as_str() {
+                    Ok(OscArg::String(s.to_string()))
+                } else {
+                    Err(OscJsonError::InvalidArg(arg.clone()))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(OscMessage { address, args })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum OscJsonError {
+    #[error("JSON 缺少 address 欄位")]
+    MissingAddress,
+    #[error("JSON 缺少 args 欄位")]
+    MissingArgs,
+    #[error("無法辨識的參數型別: {0}")]
+    InvalidArg(serde_json::Value),
 }
 
 pub struct OscRepeater {
     config: Config,
     distributor: Arc<Distributor>,
+    port_stats: std::sync::Mutex<std::collections::HashMap<u16, Arc<PortStats>>>,
 }
 
 impl OscRepeater {
     pub fn new(config: Config) -> Self {
-        let distributor = Arc::new(Distributor::new(&config.targets));
-        Self { config, distributor }
+        let distributor = Arc::new(Distributor::with_max_packet_len(
+            &config.targets,
+            config.max_packet_len,
+        ));
+        Self {
+            config,
+            distributor,
+            port_stats: std::sync::Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    // 每個監聽埠口目前的收送統計，供 main.rs 等操作者輪詢診斷
+    pub fn port_stats(&self) -> std::collections::HashMap<u16, Arc<PortStats>> {
+        self.port_stats
+            .lock()
+            .expect("port_stats mutex poisoned")
+            .clone()
     }
 
     pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
         let mut handles = Vec::new();
 
-        // Start sender tasks
-        for sender in &self.distributor.senders {
+        // Start a driver task per configured target, each pulling from its own
+        // broadcast subscription and pushing through the target's `Transport`.
+        for target_handle in self.distributor.take_targets() {
             let rx = self.distributor.subscribe();
-            let sender = sender.clone();
             let handle = tokio::spawn(async move {
-                sender.run(rx).await;
+                drive_target(target_handle, rx).await;
             });
             handles.push(handle);
         }
 
         // Create receivers for each listen port
-        for &port in &self.config.listen_ports {
+        for listen_port in &self.config.listen_ports {
+            let port = listen_port.port;
+            let mode = listen_port.decode_mode;
+
+            let sink: Arc<dyn PacketSink> = match &listen_port.coalesce {
+                Some(coalesce) => Coalescer::spawn(
+                    self.distributor.clone(),
+                    coalesce.parsed_interval()?,
+                    &coalesce.passthrough,
+                ),
+                None => self.distributor.clone(),
+            };
+
+            let handle = match listen_port.transport {
+                TransportKind::Udp => {
+                    let receiver = Receiver::new(port, sink, mode);
+                    self.port_stats
+                        .lock()
+                        .expect("port_stats mutex poisoned")
+                        .insert(port, receiver.stats());
+                    tokio::spawn(async move {
+                        if let Err(e) = receiver.run().await {
+                            eprintln!("埠口 {} 錯誤: {}", port, e);
+                        }
+                    })
+                }
+                TransportKind::Tcp => {
+                    let max_packet_len = self.config.max_packet_len;
+                    let receiver = TcpReceiver::new(port, max_packet_len, sink, mode);
+                    self.port_stats
+                        .lock()
+                        .expect("port_stats mutex poisoned")
+                        .insert(port, receiver.stats());
+                    tokio::spawn(async move {
+                        if let Err(e) = receiver.run().await {
+                            eprintln!("TCP 埠口 {} 錯誤: {}", port, e);
+                        }
+                    })
+                }
+            };
+            handles.push(handle);
+        }
+
+        // 定期把每個埠口的統計印出來，讓操作者不必自己寫輪詢程式也能看到。
+        if !self.config.listen_ports.is_empty() {
+            let port_stats = self
+                .port_stats
+                .lock()
+                .expect("port_stats mutex poisoned")
+                .clone();
+            handles.push(tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(std::time::Duration::from_secs(30));
+                loop {
+                    ticker.tick().await;
+                    for (port, stats) in &port_stats {
+                        println!(
+                            "埠口 {} 統計: received={} parsed={} dropped={:?}",
+                            port,
+                            stats.received(),
+                            stats.parsed(),
+                            stats.dropped_by_reason()
+                        );
+                    }
+                }
+            }));
+        }
+
+        // Start the MQTT bridge, if configured
+        if let Some(mqtt_config) = self.config.mqtt.clone() {
             let distributor = self.distributor.clone();
             let handle = tokio::spawn(async move {
-                if let Err(e) = Receiver::new(port, distributor).run().await {
-                    eprintln!("埠口 {} 錯誤: {}", port, e);
+                MqttBridge::new(mqtt_config, distributor).run().await;
+            });
+            handles.push(handle);
+        }
+
+        // Start the WebSocket relay, if configured
+        if let Some(ws_bind) = self.config.ws_bind {
+            let distributor = self.distributor.clone();
+            let handle = tokio::spawn(async move {
+                if let Err(e) = WsRelay::new(ws_bind, distributor).run().await {
+                    eprintln!("WebSocket 中繼錯誤: {}", e);
                 }
             });
             handles.push(handle);
@@ -275,21 +628,50 @@ impl OscRepeater {
     }
 }
 
+// 一個已設定好的目標：裝箱後的傳輸後端，加上路由規則與日誌用的標籤
+struct TargetHandle {
+    label: String,
+    route: Route,
+    transport: Box<dyn DynTransport>,
+}
+
 pub struct Distributor {
-    senders: Vec<Sender>,
+    // 用 Mutex 包著，讓 OscRepeater::run 在啟動時一次性取出並各自驅動成獨立任務
+    targets: std::sync::Mutex<Vec<TargetHandle>>,
     tx: broadcast::Sender<OscPayload>,
 }
 
 impl Distributor {
-    pub fn new(targets: &[SocketAddr]) -> Self {
+    pub fn new(targets: &[Target]) -> Self {
+        Self::with_max_packet_len(targets, DEFAULT_MAX_TCP_PACKET_LEN)
+    }
+
+    pub fn with_max_packet_len(targets: &[Target], max_packet_len: usize) -> Self {
         let (tx, _) = broadcast::channel(1000);
-        let mut senders = Vec::new();
+        let mut handles = Vec::new();
+
+        for target in targets {
+            let route = target.route();
+            let transport: Box<dyn DynTransport> = match target.transport {
+                TransportKind::Udp => Box::new(Sender::new(target.addr)),
+                TransportKind::Tcp => Box::new(TcpSender::new(target.addr, max_packet_len)),
+            };
+            handles.push(TargetHandle {
+                label: target.addr.to_string(),
+                route,
+                transport,
+            });
+        }
 
-        for &target in targets {
-            senders.push(Sender::new(target));
+        Self {
+            targets: std::sync::Mutex::new(handles),
+            tx,
         }
+    }
 
-        Self { senders, tx }
+    // 只會真正產生結果一次；重複呼叫會得到空集合
+    fn take_targets(&self) -> Vec<TargetHandle> {
+        std::mem::take(&mut *self.targets.lock().expect("targets mutex poisoned"))
     }
 
     pub fn send(&self, payload: OscPayload) {
@@ -301,62 +683,150 @@ impl Distributor {
     }
 }
 
-#[derive(Debug, Clone)]
+// 把一個目標的 broadcast 訂閱、路由規則與傳輸後端串起來的驅動迴圈
+async fn drive_target(mut handle: TargetHandle, mut rx: broadcast::Receiver<OscPayload>) {
+    loop {
+        let payload = match rx.recv().await {
+            Ok(payload) => payload,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+
+        let Some(outgoing) = route_payload(&handle.route, &payload) else {
+            continue;
+        };
+
+        if let Err(e) = handle.transport.send(&outgoing).await {
+            eprintln!("{} 發送失敗: {}", handle.label, e);
+            tokio::time::sleep(restart_backoff(e.as_ref())).await;
+        }
+    }
+}
+
+// 依實際後端與失敗情境決定重試前的等待時間；`DynTransport` 把錯誤型別抹除成
+// `Box<dyn Error + Send>`，downcast 回具體的 `UdpSenderError`/`TcpSenderError`
+// 才能區分「socket 建立不起來」這種值得久等的情況，和單次發送失敗這種應該
+// 盡快重試的情況。
+fn restart_backoff(error: &(dyn std::error::Error + Send + 'static)) -> std::time::Duration {
+    if let Some(e) = error.downcast_ref::<UdpSenderError>() {
+        match e {
+            UdpSenderError::Bind(_) => std::time::Duration::from_secs(5),
+            UdpSenderError::Connect(..) | UdpSenderError::Send(_) => {
+                std::time::Duration::from_millis(500)
+            }
+        }
+    } else if let Some(e) = error.downcast_ref::<TcpSenderError>() {
+        match e {
+            TcpSenderError::Connect(..) => std::time::Duration::from_secs(2),
+            TcpSenderError::Send(..) => std::time::Duration::from_millis(500),
+        }
+    } else {
+        std::time::Duration::from_millis(500)
+    }
+}
+
+#[derive(Debug)]
 pub struct Sender {
     target: SocketAddr,
+    socket: Option<tokio::net::UdpSocket>,
 }
 
 impl Sender {
     pub fn new(target: SocketAddr) -> Self {
-        Self { target }
+        Self {
+            target,
+            socket: None,
+        }
     }
+}
 
-    pub async fn run(self, mut rx: broadcast::Receiver<OscPayload>) {
-        let socket = match tokio::net::UdpSocket::bind("0.0.0.0:0").await {
-            Ok(socket) => socket,
-            Err(e) => {
-                eprintln!("建立送出 Socket 失敗: {}", e);
-                return;
-            }
-        };
+#[derive(Debug, Error)]
+pub enum UdpSenderError {
+    #[error("建立送出 Socket 失敗: {0}")]
+    Bind(#[source] std::io::Error),
+    #[error("連線到 {0} 失敗: {1}")]
+    Connect(SocketAddr, #[source] std::io::Error),
+    #[error("發送失敗: {0}")]
+    Send(#[source] std::io::Error),
+}
 
-        if let Err(e) = socket.connect(self.target).await {
-            eprintln!("連線到 {} 失敗: {}", self.target, e);
-            return;
+#[async_trait]
+impl Transport for Sender {
+    type Error = UdpSenderError;
+
+    async fn send(&mut self, payload: &OscPayload) -> Result<(), Self::Error> {
+        if self.socket.is_none() {
+            let socket = tokio::net::UdpSocket::bind("0.0.0.0:0")
+                .await
+                .map_err(UdpSenderError::Bind)?;
+            socket
+                .connect(self.target)
+                .await
+                .map_err(|e| UdpSenderError::Connect(self.target, e))?;
+            self.socket = Some(socket);
         }
 
-        loop {
-            match rx.recv().await {
-                Ok(payload) => {
-                    if let Err(e) = socket.send(payload.as_ref()).await {
-                        eprintln!("發送失敗: {}", e);
-                    }
-                }
-                Err(broadcast::error::RecvError::Lagged(_)) => {
-                    continue;
-                }
-                Err(broadcast::error::RecvError::Closed) => {
-                    break;
-                }
-            }
+        self.socket
+            .as_ref()
+            .expect("socket just established above")
+            .send(payload.as_ref())
+            .await
+            .map_err(UdpSenderError::Send)
+    }
+}
+
+// 依目標的路由規則決定是否轉發，以及轉發前是否要先轉換數值；
+// 沒有設定任何樣式/縮放時直接回傳原始位元組，維持盲目鏡射效能
+pub(crate) fn route_payload(route: &Route, payload: &OscPayload) -> Option<OscPayload> {
+    if route.is_passthrough() {
+        return Some(payload.clone());
+    }
+
+    // 用 Lenient 解析：封包已經在接收端依該埠口的 DecodeMode 通過驗證才
+    // 送進共用的 broadcast channel，這裡沒有保留原始埠口，用 Strict
+    // 重新解析會讓 lenient 埠口接受的封包在這裡被二次丟棄。
+    let message = match OscMessage::deserialize_with_mode(payload, DecodeMode::Lenient) {
+        Ok(message) => message,
+        Err(e) => {
+            eprintln!("路由時解析封包失敗（offset {}）: {}", e.offset(), e);
+            return None;
         }
+    };
+
+    if !route.matches(&message.address) {
+        return None;
     }
+
+    let transformed = route.transform(&message);
+    Some(Arc::from(transformed.serialize()))
 }
 
 pub struct Receiver {
     port: u16,
-    distributor: Arc<Distributor>,
+    sink: Arc<dyn PacketSink>,
+    mode: DecodeMode,
+    stats: Arc<PortStats>,
 }
 
 impl Receiver {
-    pub fn new(port: u16, distributor: Arc<Distributor>) -> Self {
-        Self { port, distributor }
+    pub fn new(port: u16, sink: Arc<dyn PacketSink>, mode: DecodeMode) -> Self {
+        Self {
+            port,
+            sink,
+            mode,
+            stats: Arc::new(PortStats::default()),
+        }
+    }
+
+    // 這個埠口的收送統計，供操作者診斷異常的發送端
+    pub fn stats(&self) -> Arc<PortStats> {
+        self.stats.clone()
     }
 
     pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
         let addr = format!("0.0.0.0:{}", self.port);
         let socket = tokio::net::UdpSocket::bind(&addr).await?;
-        
+
         println!("監聽埠口: {}", addr);
 
         let mut buf = vec![0u8; MAX_PACKET_SIZE];
@@ -369,13 +839,93 @@ impl Receiver {
                 }
             };
 
-            if let Err(e) = OscMessage::deserialize(&buf[..len]) {
-                eprintln!("解析錯誤: {}", e);
-                continue;
-            }
+            self.stats.record_received();
 
-            let payload: OscPayload = Arc::from(&buf[..len]);
-            self.distributor.send(payload);
+            match OscMessage::deserialize_with_mode(&buf[..len], self.mode) {
+                Ok(_) => {
+                    self.stats.record_parsed();
+                    let payload: OscPayload = Arc::from(&buf[..len]);
+                    self.sink.send(payload);
+                }
+                Err(e) => {
+                    self.stats.record_dropped(e.reason());
+                    eprintln!("埠口 {} 解析錯誤（offset {}）: {}", self.port, e.offset(), e);
+                }
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_a_message_with_mixed_args() {
+        let message = OscMessage::new("/foo/bar")
+            .push_int(42)
+            .push_float(1.5)
+            .push_string("hi");
+        let bytes = message.serialize();
+
+        let decoded = OscMessage::deserialize(&bytes).unwrap();
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn strict_mode_rejects_unknown_type_tag() {
+        // 位址 "/a"、type tag 字串 ",x"（'x' 不是已知標籤）
+        let mut bytes = b"/a\0\0".to_vec();
+        bytes.extend_from_slice(b",x\0\0");
+
+        let err = OscMessage::deserialize(&bytes).unwrap_err();
+        assert!(matches!(err, OscParseError::UnknownTypeTag { tag: b'x', .. }));
+        assert_eq!(err.reason(), "unknown_type_tag");
+    }
+
+    #[test]
+    fn lenient_mode_skips_zero_width_unknown_tag_but_keeps_known_args() {
+        // type tag 字串 ",Ii"：零資料寬度的 'I'（Infinitum）被跳過，已知的 'i' 仍然解析
+        let mut bytes = b"/a\0\0".to_vec();
+        bytes.extend_from_slice(b",Ii\0");
+        bytes.extend_from_slice(&7i32.to_be_bytes());
+
+        let message =
+            OscMessage::deserialize_with_mode(&bytes, DecodeMode::Lenient).unwrap();
+        assert_eq!(message.args, vec![OscArg::Int(7)]);
+    }
+
+    #[test]
+    fn lenient_mode_rejects_unknown_tag_that_may_carry_data() {
+        // type tag 字串 ",b"：未實作的 'b'（blob）可能帶有資料，Lenient 也必須拒絕，
+        // 否則貿然跳過會讓 `pos` 錯位並汙染後續參數。
+        let mut bytes = b"/a\0\0".to_vec();
+        bytes.extend_from_slice(b",b\0\0");
+
+        let err =
+            OscMessage::deserialize_with_mode(&bytes, DecodeMode::Lenient).unwrap_err();
+        assert!(matches!(err, OscParseError::UnknownTypeTag { tag: b'b', .. }));
+    }
+
+    #[test]
+    fn deserialize_too_short_reports_offset_zero() {
+        let err = OscMessage::deserialize(b"ab").unwrap_err();
+        assert_eq!(err, OscParseError::TooShort { offset: 0 });
+    }
+
+    #[test]
+    fn deserialize_truncated_int_arg_reports_offset() {
+        // 位址 "/a"、type tag ",i"，但沒有附上 4 位元組的整數資料
+        let mut bytes = b"/a\0\0".to_vec();
+        bytes.extend_from_slice(b",i\0\0");
+
+        let err = OscMessage::deserialize(&bytes).unwrap_err();
+        assert_eq!(
+            err,
+            OscParseError::TruncatedArg {
+                tag: b'i',
+                offset: bytes.len(),
+            }
+        );
+    }
+}
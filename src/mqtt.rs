@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use tokio::sync::broadcast;
+
+use crate::{Distributor, OscMessage, OscPayload};
+
+// 標記在發布的 JSON 裡的欄位，讓 `direction: both` 時能辨認出 broker echo
+// 回來的是自己剛發布的訊息，避免注入回 Distributor 造成無限迴圈。
+const BRIDGE_ORIGIN_FIELD: &str = "_mqtt_bridge_origin";
+
+// 橋接方向：發布到 MQTT、從 MQTT 訂閱注入，或兩者皆要
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MqttDirection {
+    Publish,
+    Subscribe,
+    Both,
+}
+
+impl MqttDirection {
+    fn should_publish(self) -> bool {
+        matches!(self, MqttDirection::Publish | MqttDirection::Both)
+    }
+
+    fn should_subscribe(self) -> bool {
+        matches!(self, MqttDirection::Subscribe | MqttDirection::Both)
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MqttConfig {
+    pub broker_url: String,
+    pub topic_prefix: String,
+    pub direction: MqttDirection,
+}
+
+// 讓轉發器與 MQTT broker 互通：發布經過的 OSC 訊息，並把 broker 收到的訊息注入回 Distributor
+pub struct MqttBridge {
+    config: MqttConfig,
+    distributor: Arc<Distributor>,
+}
+
+impl MqttBridge {
+    pub fn new(config: MqttConfig, distributor: Arc<Distributor>) -> Self {
+        Self { config, distributor }
+    }
+
+    pub async fn run(&self) {
+        let mut options = match MqttOptions::parse_url(self.config.broker_url.clone()) {
+            Ok(options) => options,
+            Err(e) => {
+                eprintln!("無效的 MQTT broker URL {}: {}", self.config.broker_url, e);
+                return;
+            }
+        };
+        options.set_keep_alive(Duration::from_secs(30));
+
+        let (client, mut eventloop) = AsyncClient::new(options, 100);
+
+        if self.config.direction.should_subscribe() {
+            let topic = format!("{}/#", self.config.topic_prefix);
+            if let Err(e) = client.subscribe(&topic, QoS::AtLeastOnce).await {
+                eprintln!("訂閱 {} 失敗: {}", topic, e);
+            }
+        }
+
+        if self.config.direction.should_publish() {
+            let rx = self.distributor.subscribe();
+            let client = client.clone();
+            let prefix = self.config.topic_prefix.clone();
+            tokio::spawn(async move {
+                publish_loop(rx, client, prefix).await;
+            });
+        }
+
+        loop {
+            match eventloop.poll().await {
+                Ok(Event::Incoming(Packet::Publish(publish))) => {
+                    if self.config.direction.should_subscribe() {
+                        handle_incoming(&publish.payload, &self.distributor);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("MQTT 連線錯誤: {}，稍後重試", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+}
+
+async fn publish_loop(
+    mut rx: broadcast::Receiver<OscPayload>,
+    client: AsyncClient,
+    topic_prefix: String,
+) {
+    loop {
+        let payload = match rx.recv().await {
+            Ok(payload) => payload,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+
+        let message = match OscMessage::deserialize(&payload) {
+            Ok(message) => message,
+            Err(e) => {
+                eprintln!("MQTT 發布時解析封包失敗: {}", e);
+                continue;
+            }
+        };
+
+        let topic = format!("{}{}", topic_prefix, message.address);
+        let mut json = message.to_json();
+        json[BRIDGE_ORIGIN_FIELD] = serde_json::json!(true);
+        let json = serde_json::to_vec(&json).expect("OSC JSON 序列化不應失敗");
+
+        if let Err(e) = client.publish(topic, QoS::AtLeastOnce, false, json).await {
+            eprintln!("MQTT 發布失敗: {}", e);
+        }
+    }
+}
+
+fn handle_incoming(payload: &[u8], distributor: &Arc<Distributor>) {
+    let value: serde_json::Value = match serde_json::from_slice(payload) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("MQTT 訊息不是合法的 JSON: {}", e);
+            return;
+        }
+    };
+
+    // broker 在 `both` 模式下會把這個 bridge 自己發布的訊息原樣 echo 回來
+    // （沒有設定 no_local），帶著標記的訊息直接忽略，否則會無限迴圈發布。
+    if value.get(BRIDGE_ORIGIN_FIELD).is_some() {
+        return;
+    }
+
+    let message = match OscMessage::from_json(&value) {
+        Ok(message) => message,
+        Err(e) => {
+            eprintln!("MQTT 訊息轉換為 OSC 失敗: {}", e);
+            return;
+        }
+    };
+
+    let payload: OscPayload = Arc::from(message.serialize());
+    distributor.send(payload);
+}
@@ -0,0 +1,303 @@
+use crate::{OscArg, OscMessage};
+
+// 支援 `*`、`?`、`[a-z]`、`{foo,bar}` 的萬用字元位址樣式。
+#[derive(Debug, Clone)]
+struct CompiledPattern {
+    // `{...}` 展開後的各個變體，已先 tokenize。
+    variants: Vec<Vec<Token>>,
+}
+
+impl CompiledPattern {
+    fn new(pattern: &str) -> Self {
+        Self {
+            variants: expand_braces(pattern)
+                .iter()
+                .map(|variant| tokenize(variant.as_bytes()))
+                .collect(),
+        }
+    }
+
+    fn matches(&self, address: &str) -> bool {
+        self.variants
+            .iter()
+            .any(|tokens| glob_match(tokens, address.as_bytes()))
+    }
+}
+
+// 展開 `{a,b,c}` 成多個具體樣式
+fn expand_braces(pattern: &str) -> Vec<String> {
+    if let Some(open) = pattern.find('{') {
+        if let Some(close_rel) = pattern[open..].find('}') {
+            let close = open + close_rel;
+            let prefix = &pattern[..open];
+            let alternatives = &pattern[open + 1..close];
+            let suffix = &pattern[close + 1..];
+
+            return alternatives
+                .split(',')
+                .flat_map(|alt| expand_braces(&format!("{}{}{}", prefix, alt, suffix)))
+                .collect();
+        }
+    }
+    vec![pattern.to_string()]
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    Star,
+    Any,
+    Literal(u8),
+    Class { negate: bool, members: Vec<ClassMember> },
+}
+
+#[derive(Debug, Clone)]
+enum ClassMember {
+    Single(u8),
+    Range(u8, u8),
+}
+
+fn tokenize(pattern: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < pattern.len() {
+        match pattern[i] {
+            b'*' => {
+                tokens.push(Token::Star);
+                i += 1;
+            }
+            b'?' => {
+                tokens.push(Token::Any);
+                i += 1;
+            }
+            b'[' => {
+                let Some(close_rel) = pattern[i..].iter().position(|&b| b == b']') else {
+                    // 沒有對應的 `]`，當字面量
+                    tokens.push(Token::Literal(b'['));
+                    i += 1;
+                    continue;
+                };
+                let close = i + close_rel;
+                let mut class = &pattern[i + 1..close];
+                let negate = matches!(class.first(), Some(b'^') | Some(b'!'));
+                if negate {
+                    class = &class[1..];
+                }
+
+                let mut members = Vec::new();
+                let mut j = 0;
+                while j < class.len() {
+                    if j + 2 < class.len() && class[j + 1] == b'-' {
+                        members.push(ClassMember::Range(class[j], class[j + 2]));
+                        j += 3;
+                    } else {
+                        members.push(ClassMember::Single(class[j]));
+                        j += 1;
+                    }
+                }
+                tokens.push(Token::Class { negate, members });
+                i = close + 1;
+            }
+            c => {
+                tokens.push(Token::Literal(c));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+fn token_matches_char(token: &Token, ch: u8) -> bool {
+    match token {
+        Token::Star => false,
+        Token::Any => true,
+        Token::Literal(c) => *c == ch,
+        Token::Class { negate, members } => {
+            let matched = members.iter().any(|m| match m {
+                ClassMember::Single(c) => *c == ch,
+                ClassMember::Range(lo, hi) => *lo <= ch && ch <= *hi,
+            });
+            matched != *negate
+        }
+    }
+}
+
+// 迭代雙指標比對，只記住最近一個 `*`；O(pattern * text)，避免遞迴
+// backtracking 在病態輸入（位址來自網路）上指數爆炸。
+fn glob_match(tokens: &[Token], text: &[u8]) -> bool {
+    let (mut ti, mut si) = (0usize, 0usize);
+    let mut star: Option<(usize, usize)> = None; // (token 位置, 當時的文字位置)
+
+    while si < text.len() {
+        if ti < tokens.len() && token_matches_char(&tokens[ti], text[si]) {
+            ti += 1;
+            si += 1;
+        } else if ti < tokens.len() && matches!(tokens[ti], Token::Star) {
+            star = Some((ti, si));
+            ti += 1;
+        } else if let Some((star_ti, star_si)) = star {
+            let retry_from = star_si + 1;
+            ti = star_ti + 1;
+            si = retry_from;
+            star = Some((star_ti, retry_from));
+        } else {
+            return false;
+        }
+    }
+
+    while ti < tokens.len() && matches!(tokens[ti], Token::Star) {
+        ti += 1;
+    }
+
+    ti == tokens.len()
+}
+
+// 名單式比對：留空代表沒有任何位址符合（與 `Route` 留空代表全部放行相反）。
+#[derive(Debug, Clone)]
+pub struct PatternSet {
+    patterns: Vec<CompiledPattern>,
+}
+
+impl PatternSet {
+    pub fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: patterns.iter().map(|p| CompiledPattern::new(p)).collect(),
+        }
+    }
+
+    pub fn matches(&self, address: &str) -> bool {
+        self.patterns.iter().any(|p| p.matches(address))
+    }
+}
+
+// 位址樣式過濾 + 數值縮放/偏移。沒設定樣式時視為全部放行（盲目鏡射）。
+#[derive(Debug, Clone)]
+pub struct Route {
+    patterns: Vec<CompiledPattern>,
+    scale: Option<f64>,
+    offset: Option<f64>,
+}
+
+impl Route {
+    pub fn new(patterns: &[String], scale: Option<f64>, offset: Option<f64>) -> Self {
+        Self {
+            patterns: patterns.iter().map(|p| CompiledPattern::new(p)).collect(),
+            scale,
+            offset,
+        }
+    }
+
+    // 盲目鏡射：可以略過解析直接轉發原始位元組。
+    pub fn is_passthrough(&self) -> bool {
+        self.patterns.is_empty() && self.scale.is_none() && self.offset.is_none()
+    }
+
+    pub fn matches(&self, address: &str) -> bool {
+        self.patterns.is_empty() || self.patterns.iter().any(|p| p.matches(address))
+    }
+
+    // 依 scale/offset 轉換數值型參數，其餘參數原樣保留
+    pub fn transform(&self, message: &OscMessage) -> OscMessage {
+        let scale = self.scale.unwrap_or(1.0);
+        let offset = self.offset.unwrap_or(0.0);
+
+        let args = message
+            .args
+            .iter()
+            .map(|arg| match arg {
+                OscArg::Int(value) => {
+                    OscArg::Int(((*value as f64) * scale + offset).round() as i32)
+                }
+                OscArg::Float(value) => {
+                    OscArg::Float(((*value as f64) * scale + offset) as f32)
+                }
+                other => other.clone(),
+            })
+            .collect();
+
+        OscMessage {
+            address: message.address.clone(),
+            args,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, address: &str) -> bool {
+        CompiledPattern::new(pattern).matches(address)
+    }
+
+    #[test]
+    fn star_matches_any_length() {
+        assert!(matches("/light/*", "/light/1"));
+        assert!(matches("/light/*", "/light/"));
+        assert!(matches("/light/**", "/light/1/2/3"));
+        assert!(!matches("/light/*", "/other/1"));
+    }
+
+    #[test]
+    fn star_does_not_match_when_suffix_required() {
+        assert!(matches("/light/*/on", "/light/1/on"));
+        assert!(!matches("/light/*/on", "/light/1/off"));
+    }
+
+    #[test]
+    fn question_mark_matches_single_char() {
+        assert!(matches("/ch?", "/ch1"));
+        assert!(!matches("/ch?", "/ch12"));
+        assert!(!matches("/ch?", "/ch"));
+    }
+
+    #[test]
+    fn char_class_matches_range_and_negation() {
+        assert!(matches("/ch[0-9]", "/ch5"));
+        assert!(!matches("/ch[0-9]", "/cha"));
+        assert!(matches("/ch[^0-9]", "/cha"));
+        assert!(!matches("/ch[^0-9]", "/ch5"));
+    }
+
+    #[test]
+    fn brace_alternation_expands_to_variants() {
+        assert!(matches("/{foo,bar}/1", "/foo/1"));
+        assert!(matches("/{foo,bar}/1", "/bar/1"));
+        assert!(!matches("/{foo,bar}/1", "/baz/1"));
+    }
+
+    #[test]
+    fn pathological_input_does_not_hang() {
+        // 多個 `*` 配上不匹配、重複性高的長位址：若退化回遞迴
+        // backtracking，這種輸入會指數爆炸；迭代演算法應瞬間回傳。
+        let pattern = "/a*a*a*a*a*a*a*a*a*a*b";
+        let address = format!("/{}", "a".repeat(30));
+        assert!(!matches(pattern, &address));
+    }
+
+    #[test]
+    fn pattern_set_empty_matches_nothing() {
+        let set = PatternSet::new(&[]);
+        assert!(!set.matches("/anything"));
+    }
+
+    #[test]
+    fn route_empty_patterns_matches_everything() {
+        let route = Route::new(&[], None, None);
+        assert!(route.is_passthrough());
+        assert!(route.matches("/anything"));
+    }
+
+    #[test]
+    fn route_transform_scales_numeric_args() {
+        let route = Route::new(&[], Some(2.0), Some(1.0));
+        assert!(!route.is_passthrough());
+        let message = OscMessage {
+            address: "/fader".to_string(),
+            args: vec![OscArg::Int(10), OscArg::Float(1.5), OscArg::String("x".into())],
+        };
+        let transformed = route.transform(&message);
+        assert_eq!(transformed.args[0], OscArg::Int(21));
+        assert_eq!(transformed.args[1], OscArg::Float(4.0));
+        assert_eq!(transformed.args[2], OscArg::String("x".into()));
+    }
+}
@@ -0,0 +1,251 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use thiserror::Error;
+use tokio::net::{TcpListener, TcpStream};
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder, Framed};
+use futures::{SinkExt, StreamExt};
+
+use crate::{DecodeMode, OscMessage, OscPayload, PacketSink, PortStats};
+use crate::transport::Transport;
+
+// 以 4 位元組大端長度前綴為每個 OSC 封包定界。只負責切 frame 邊界，
+// frame 內容是不是合法 OSC 訊息是上層（`handle_connection`）的事。
+#[derive(Debug, Clone)]
+pub struct OscFrameCodec {
+    max_packet_len: usize,
+}
+
+impl OscFrameCodec {
+    pub fn new(max_packet_len: usize) -> Self {
+        Self { max_packet_len }
+    }
+}
+
+impl Decoder for OscFrameCodec {
+    type Item = Vec<u8>;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.len() < 4 {
+            return Ok(None);
+        }
+
+        let len = u32::from_be_bytes([src[0], src[1], src[2], src[3]]) as usize;
+
+        if len > self.max_packet_len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("封包長度 {} 超過上限 {}", len, self.max_packet_len),
+            ));
+        }
+
+        if src.len() < 4 + len {
+            // 預留空間，避免重複配置
+            src.reserve(4 + len - src.len());
+            return Ok(None);
+        }
+
+        src.advance(4);
+        Ok(Some(src.split_to(len).to_vec()))
+    }
+}
+
+impl Encoder<Vec<u8>> for OscFrameCodec {
+    type Error = std::io::Error;
+
+    fn encode(&mut self, item: Vec<u8>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.reserve(4 + item.len());
+        dst.put_u32(item.len() as u32);
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+// 監聽一個 TCP 埠口，將每條連線送進來的封包轉交給下游的 `PacketSink`
+pub struct TcpReceiver {
+    port: u16,
+    max_packet_len: usize,
+    sink: Arc<dyn PacketSink>,
+    mode: DecodeMode,
+    stats: Arc<PortStats>,
+}
+
+impl TcpReceiver {
+    pub fn new(
+        port: u16,
+        max_packet_len: usize,
+        sink: Arc<dyn PacketSink>,
+        mode: DecodeMode,
+    ) -> Self {
+        Self {
+            port,
+            max_packet_len,
+            sink,
+            mode,
+            stats: Arc::new(PortStats::default()),
+        }
+    }
+
+    pub fn stats(&self) -> Arc<PortStats> {
+        self.stats.clone()
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let addr = format!("0.0.0.0:{}", self.port);
+        let listener = TcpListener::bind(&addr).await?;
+
+        println!("監聽 TCP 埠口: {}", addr);
+
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    eprintln!("TCP 接受連線錯誤: {}", e);
+                    continue;
+                }
+            };
+
+            let sink = self.sink.clone();
+            let max_packet_len = self.max_packet_len;
+            let mode = self.mode;
+            let stats = self.stats.clone();
+            tokio::spawn(async move {
+                handle_connection(stream, peer, max_packet_len, sink, mode, stats).await;
+            });
+        }
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    max_packet_len: usize,
+    sink: Arc<dyn PacketSink>,
+    mode: DecodeMode,
+    stats: Arc<PortStats>,
+) {
+    let mut framed = Framed::new(stream, OscFrameCodec::new(max_packet_len));
+
+    while let Some(result) = framed.next().await {
+        match result {
+            Ok(frame) => {
+                stats.record_received();
+                match OscMessage::deserialize_with_mode(&frame, mode) {
+                    Ok(_) => {
+                        stats.record_parsed();
+                        let payload: OscPayload = Arc::from(frame);
+                        sink.send(payload);
+                    }
+                    Err(e) => stats.record_dropped(e.reason()),
+                }
+            }
+            Err(e) => {
+                // 這裡是真正的定界錯誤（長度前綴超過上限等），連線的位元組
+                // 對齊已經不可信，只能斷線，而不是單一封包解析失敗。
+                eprintln!("TCP 連線 {} 定界錯誤: {}", peer, e);
+                break;
+            }
+        }
+    }
+}
+
+// 連線在需要時（第一次送出、或前一次送出失敗後）才（重新）建立
+pub struct TcpSender {
+    target: SocketAddr,
+    max_packet_len: usize,
+    framed: Option<Framed<TcpStream, OscFrameCodec>>,
+}
+
+impl TcpSender {
+    pub fn new(target: SocketAddr, max_packet_len: usize) -> Self {
+        Self {
+            target,
+            max_packet_len,
+            framed: None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TcpSenderError {
+    #[error("連線到 {0} 失敗: {1}")]
+    Connect(SocketAddr, #[source] std::io::Error),
+    #[error("發送到 {0} 失敗: {1}")]
+    Send(SocketAddr, #[source] std::io::Error),
+}
+
+#[async_trait]
+impl Transport for TcpSender {
+    type Error = TcpSenderError;
+
+    async fn send(&mut self, payload: &OscPayload) -> Result<(), Self::Error> {
+        if self.framed.is_none() {
+            let stream = TcpStream::connect(self.target)
+                .await
+                .map_err(|e| TcpSenderError::Connect(self.target, e))?;
+            self.framed = Some(Framed::new(stream, OscFrameCodec::new(self.max_packet_len)));
+        }
+
+        let framed = self.framed.as_mut().expect("framed just established above");
+        match framed.send(payload.to_vec()).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                // 連線已經壞掉，丟掉它讓下一次送出重新連線。
+                self.framed = None;
+                Err(TcpSenderError::Send(self.target, e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_then_decodes_a_frame() {
+        let mut codec = OscFrameCodec::new(1024);
+        let mut buf = BytesMut::new();
+        codec.encode(b"hello".to_vec(), &mut buf).unwrap();
+
+        let frame = codec.decode(&mut buf).unwrap();
+        assert_eq!(frame, Some(b"hello".to_vec()));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn decode_waits_for_a_full_frame() {
+        let mut codec = OscFrameCodec::new(1024);
+        let mut buf = BytesMut::new();
+        codec.encode(b"hello".to_vec(), &mut buf).unwrap();
+
+        // 只餵前 6 個位元組（4 位元組長度 + 2 個內容位元組），frame 還沒到齊。
+        let mut partial = BytesMut::from(&buf[..6]);
+        assert_eq!(codec.decode(&mut partial).unwrap(), None);
+    }
+
+    #[test]
+    fn decode_handles_back_to_back_frames() {
+        let mut codec = OscFrameCodec::new(1024);
+        let mut buf = BytesMut::new();
+        codec.encode(b"one".to_vec(), &mut buf).unwrap();
+        codec.encode(b"two".to_vec(), &mut buf).unwrap();
+
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(b"one".to_vec()));
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(b"two".to_vec()));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+    }
+
+    #[test]
+    fn decode_rejects_frame_over_max_len() {
+        let mut codec = OscFrameCodec::new(4);
+        let mut buf = BytesMut::new();
+        buf.put_u32(5);
+        buf.extend_from_slice(b"12345");
+
+        assert!(codec.decode(&mut buf).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+use std::error::Error as StdError;
+
+use async_trait::async_trait;
+
+use crate::OscPayload;
+
+// 每種後端（UDP、TCP、……）各自定義關聯的錯誤型別，方便比對具體失敗情境。
+#[async_trait]
+pub trait Transport: Send {
+    type Error: StdError + Send + 'static;
+
+    async fn send(&mut self, payload: &OscPayload) -> Result<(), Self::Error>;
+
+    // 預設永不完成：只有需要接收方向的後端才需要覆寫
+    async fn recv(&mut self) -> Result<OscPayload, Self::Error> {
+        std::future::pending::<Result<OscPayload, Self::Error>>().await
+    }
+}
+
+// 抹除 `Transport` 的關聯錯誤型別，讓 `Distributor` 能用同一個
+// `Vec<Box<dyn DynTransport>>` 驅動所有後端。
+#[async_trait]
+pub trait DynTransport: Send {
+    async fn send(&mut self, payload: &OscPayload) -> Result<(), Box<dyn StdError + Send>>;
+    async fn recv(&mut self) -> Result<OscPayload, Box<dyn StdError + Send>>;
+}
+
+#[async_trait]
+impl<T> DynTransport for T
+where
+    T: Transport + Send,
+{
+    async fn send(&mut self, payload: &OscPayload) -> Result<(), Box<dyn StdError + Send>> {
+        Transport::send(self, payload)
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn StdError + Send>)
+    }
+
+    async fn recv(&mut self) -> Result<OscPayload, Box<dyn StdError + Send>> {
+        Transport::recv(self)
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn StdError + Send>)
+    }
+}
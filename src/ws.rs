@@ -0,0 +1,99 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use futures::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{Distributor, OscMessage, OscPayload};
+
+// 讓遠端客戶端透過單一 WebSocket 連線收送 OSC，穿越對 UDP 不友善的網路
+pub struct WsRelay {
+    bind_addr: SocketAddr,
+    distributor: Arc<Distributor>,
+}
+
+impl WsRelay {
+    pub fn new(bind_addr: SocketAddr, distributor: Arc<Distributor>) -> Self {
+        Self {
+            bind_addr,
+            distributor,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let listener = TcpListener::bind(self.bind_addr).await?;
+
+        println!("監聽 WebSocket: {}", self.bind_addr);
+
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    eprintln!("WebSocket 接受連線錯誤: {}", e);
+                    continue;
+                }
+            };
+
+            let distributor = self.distributor.clone();
+            tokio::spawn(async move {
+                handle_connection(stream, peer, distributor).await;
+            });
+        }
+    }
+}
+
+async fn handle_connection(stream: TcpStream, peer: SocketAddr, distributor: Arc<Distributor>) {
+    let ws_stream = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws_stream) => ws_stream,
+        Err(e) => {
+            eprintln!("WebSocket 升級失敗 {}: {}", peer, e);
+            return;
+        }
+    };
+
+    let (mut outgoing, mut incoming) = ws_stream.split();
+    let mut rx = distributor.subscribe();
+
+    let mut forward_task = tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(payload) => {
+                    if outgoing.send(Message::Binary(payload.to_vec())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    loop {
+        tokio::select! {
+            message = incoming.next() => {
+                match message {
+                    Some(Ok(Message::Binary(data))) => {
+                        if let Err(e) = OscMessage::deserialize(&data) {
+                            eprintln!("WebSocket 客戶端 {} 送出無效封包: {}", peer, e);
+                            continue;
+                        }
+                        let payload: OscPayload = Arc::from(data);
+                        distributor.send(payload);
+                    }
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        eprintln!("WebSocket 連線 {} 錯誤: {}", peer, e);
+                        break;
+                    }
+                }
+            }
+            _ = &mut forward_task => {
+                break;
+            }
+        }
+    }
+
+    forward_task.abort();
+}